@@ -22,9 +22,20 @@ pub struct ClipCliArgs {
     /// Only points inside the intersection (if there is one) of
     /// the polygons will be included. By default, points in any of
     /// the polygons are included.
-    #[arg(long)]
+    #[arg(long, conflicts_with_all = ["xor", "at_least"])]
     pub intersect: bool,
-    
+
+    /// Only points inside an odd number of polygons will be included
+    /// (the symmetric difference of all the polygons)
+    #[arg(long, conflicts_with_all = ["intersect", "at_least"])]
+    pub xor: bool,
+
+    /// Only points inside at least this many polygons will be included.
+    /// 1 is equivalent to the default union and the polygon count is
+    /// equivalent to --intersect
+    #[arg(long, conflicts_with_all = ["intersect", "xor"])]
+    pub at_least: Option<usize>,
+
     /// The size of the chuck (number of points) that will be read
     /// per iteration while processing
     #[arg(long, default_value_t = 1_234_567)]
@@ -33,4 +44,29 @@ pub struct ClipCliArgs {
     /// The number of threads. If 0, all avaialble cores will be used
     #[arg(long, default_value_t = 0)]
     pub threads: usize,
+
+    /// Override the CRS of the shapefile (e.g. "EPSG:4326" or "UTM:33N").
+    /// By default it is read from the sidecar ".prj" file.
+    #[arg(long)]
+    pub shapefile_crs: Option<String>,
+
+    /// Override the CRS of the las file (e.g. "EPSG:32633" or "UTM:33N").
+    /// By default it is read from the header's GeoTIFF VLR.
+    #[arg(long)]
+    pub las_crs: Option<String>,
+
+    /// Grow (positive) or shrink (negative) every loaded polygon by this
+    /// distance, in the units of the las CRS, before clipping
+    #[arg(long, default_value_t = 0.0)]
+    pub buffer: f64,
+
+    /// Treat polyline shapes in the shapefile as corridors: buffer each
+    /// line to both sides by this half-width to build a clip polygon
+    #[arg(long, default_value_t = 0.0)]
+    pub corridor: f64,
+
+    /// Simplify every polygon ring with Douglas-Peucker before clipping,
+    /// discarding vertices within this distance of the simplified line
+    #[arg(long, default_value_t = 0.0)]
+    pub simplify: f64,
 }
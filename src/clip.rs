@@ -1,13 +1,17 @@
 pub extern crate las;
 pub extern crate shapefile;
+use crate::crs::{self, Crs};
+use crate::spatial_index::PolygonIndex;
 use anyhow::{Context, Ok, Result};
 use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
 use las::{Header, Point as lasPoint, Read, Reader, Write, Writer};
 use num_format::{Locale, ToFormattedString};
 use rayon::prelude::*;
 use shapefile::record::polygon::GenericPolygon;
+use shapefile::record::polyline::GenericPolyline;
 use shapefile::record::traits::{GrowablePoint, HasXY, ShrinkablePoint};
 use shapefile::{Point, Polygon, PolygonRing, Shape};
+use std::f64::consts::PI;
 use std::fmt;
 use std::io::{self, Write as StdWrte};
 use std::path::Path;
@@ -17,15 +21,32 @@ use std::time::{Duration, Instant};
 pub enum Strategy {
     Union,
     Intersection,
+    /// Point lies inside an odd number of polygons (symmetric difference).
+    Xor,
+    /// Point lies inside at least `k` polygons; generalizes `Union` (k=1)
+    /// and `Intersection` (k=polygons.len()).
+    AtLeast(usize),
 }
 
 impl fmt::Display for Strategy {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let strategy_str = match self {
-            Strategy::Intersection => "Intersection",
-            Strategy::Union => "Union",
-        };
-        write!(f, "{strategy_str}")
+        match self {
+            Strategy::Intersection => write!(f, "Intersection"),
+            Strategy::Union => write!(f, "Union"),
+            Strategy::Xor => write!(f, "Xor"),
+            Strategy::AtLeast(k) => write!(f, "AtLeast({k})"),
+        }
+    }
+}
+
+/// Whether a point contained in `inside_count` of `total_polygons` loaded
+/// polygons matches `strategy`, before the `--external` inversion.
+fn strategy_matches(strategy: Strategy, inside_count: usize, total_polygons: usize) -> bool {
+    match strategy {
+        Strategy::Union => inside_count >= 1,
+        Strategy::Intersection => inside_count == total_polygons,
+        Strategy::Xor => inside_count % 2 == 1,
+        Strategy::AtLeast(k) => inside_count >= k,
     }
 }
 
@@ -37,12 +58,18 @@ where
         .rings()
         .iter()
         .map(|ring| {
-            PolygonRing::Outer(
-                ring.points()
-                    .iter()
-                    .map(|&p| Point::new(p.x(), p.y()))
-                    .collect::<Vec<Point>>(),
-            )
+            let points = ring
+                .points()
+                .iter()
+                .map(|&p| Point::new(p.x(), p.y()))
+                .collect::<Vec<Point>>();
+            // Preserve the outer/inner (hole) classification the shapefile
+            // crate already derived from the ring's winding order instead of
+            // collapsing every ring to Outer.
+            match ring {
+                PolygonRing::Outer(_) => PolygonRing::Outer(points),
+                PolygonRing::Inner(_) => PolygonRing::Inner(points),
+            }
         })
         .collect::<Vec<PolygonRing<Point>>>();
     Polygon::with_rings(poly_rings)
@@ -53,14 +80,17 @@ fn transform_point(point: &mut Point, offsets: &[f64; 2]) {
     point.y -= offsets[1];
 }
 
-pub fn winding_number(point: &Point, polygon: &Polygon, offsets: &[f64; 2]) -> i32 {
+/// Winding number of `point` with respect to a set of directed edges, be it
+/// all the edges of a polygon or just the subset a [`PolygonIndex`] deems
+/// relevant to the point's `y`.
+pub fn winding_number_over_edges(point: &Point, edges: &[(Point, Point)], offsets: &[f64; 2]) -> i32 {
     let mut wn = 0;
     let mut point: Point = Point::new(point.x, point.y);
     transform_point(&mut point, offsets);
-    for window in polygon.rings()[0].points().windows(2) {
-        let mut p1: Point = window.first().unwrap().clone();
+    for &(p1, p2) in edges {
+        let mut p1: Point = p1;
         transform_point(&mut p1, offsets);
-        let mut p2: Point = window.last().unwrap().clone();
+        let mut p2: Point = p2;
         transform_point(&mut p2, offsets);
 
         if point.x > p1.x && point.x > p2.x {
@@ -89,42 +119,504 @@ pub fn winding_number(point: &Point, polygon: &Polygon, offsets: &[f64; 2]) -> i
     wn
 }
 
-pub fn is_point_in_poly(point: &Point, polygon: &Polygon, offsets: &[f64; 2]) -> bool {
-    winding_number(point, polygon, offsets) != 0
+/// Number of arc segments per quadrant (90 degrees) of a round join or cap.
+const BUFFER_ARC_SEGMENTS_PER_QUADRANT: usize = 4;
+
+fn rotate_minus90(v: (f64, f64)) -> (f64, f64) {
+    (v.1, -v.0)
+}
+
+fn normalize(v: (f64, f64)) -> (f64, f64) {
+    let len = (v.0 * v.0 + v.1 * v.1).sqrt();
+    if len == 0.0 {
+        (0.0, 0.0)
+    } else {
+        (v.0 / len, v.1 / len)
+    }
+}
+
+/// Shoelace signed area of a (possibly unclosed) ring; positive for
+/// counter-clockwise winding.
+fn signed_area(points: &[Point]) -> f64 {
+    let mut area = 0.0;
+    for w in points.windows(2) {
+        area += w[0].x * w[1].y - w[1].x * w[0].y;
+    }
+    // Closing edge; contributes nothing when first point == last point.
+    if let (Some(first), Some(last)) = (points.first(), points.last()) {
+        area += last.x * first.y - first.x * last.y;
+    }
+    area / 2.0
+}
+
+/// Intersection of the infinite lines through `(a, b)` and `(c, d)`, or
+/// `None` if they are parallel.
+fn line_intersection(a: Point, b: Point, c: Point, d: Point) -> Option<Point> {
+    let r = (b.x - a.x, b.y - a.y);
+    let s = (d.x - c.x, d.y - c.y);
+    let denom = r.0 * s.1 - r.1 * s.0;
+    if denom.abs() < 1e-9 {
+        return None;
+    }
+    let t = ((c.x - a.x) * s.1 - (c.y - a.y) * s.0) / denom;
+    Some(Point::new(a.x + t * r.0, a.y + t * r.1))
+}
+
+/// Offsets a single closed ring (first point == last point) by `dist`:
+/// positive dilates outward, negative erodes inward. Corners where the two
+/// neighbouring offset edges move apart (convex corners when dilating,
+/// reflex ones when eroding) get a round join (an arc of points at radius
+/// `|dist|` around the original vertex); the other corners are reconnected
+/// by intersecting the two neighbouring offset edges.
+fn offset_ring(points: &[Point], dist: f64) -> Vec<Point> {
+    let n = points.len().saturating_sub(1);
+    if n < 3 || dist == 0.0 {
+        return points.to_vec();
+    }
+    let orientation = if signed_area(points) < 0.0 { -1.0 } else { 1.0 };
+
+    let offset_edges: Vec<(Point, Point)> = (0..n)
+        .map(|i| {
+            let p1 = points[i];
+            let p2 = points[i + 1];
+            let dir = normalize((p2.x - p1.x, p2.y - p1.y));
+            let outward = rotate_minus90(dir);
+            let shift = (outward.0 * orientation * dist, outward.1 * orientation * dist);
+            (
+                Point::new(p1.x + shift.0, p1.y + shift.1),
+                Point::new(p2.x + shift.0, p2.y + shift.1),
+            )
+        })
+        .collect();
+
+    let mut result: Vec<Point> = Vec::with_capacity(n * 2);
+    for i in 0..n {
+        let prev = offset_edges[(i + n - 1) % n];
+        let cur = offset_edges[i];
+        let vertex = points[i];
+
+        let incoming = normalize((vertex.x - points[(i + n - 1) % n].x, vertex.y - points[(i + n - 1) % n].y));
+        let outgoing = normalize((points[i + 1].x - vertex.x, points[i + 1].y - vertex.y));
+        let cross = incoming.0 * outgoing.1 - incoming.1 * outgoing.0;
+        let convex = cross * orientation * dist.signum() > 0.0;
+
+        if convex {
+            result.push(prev.1);
+            let start_angle = (prev.1.y - vertex.y).atan2(prev.1.x - vertex.x);
+            let mut end_angle = (cur.0.y - vertex.y).atan2(cur.0.x - vertex.x);
+            let mut delta = end_angle - start_angle;
+            while delta <= -PI {
+                delta += 2.0 * PI;
+            }
+            while delta > PI {
+                delta -= 2.0 * PI;
+            }
+            end_angle = start_angle + delta;
+            let radius = dist.abs();
+            let steps = ((BUFFER_ARC_SEGMENTS_PER_QUADRANT as f64) * (delta.abs() / (PI / 2.0)))
+                .ceil()
+                .max(1.0) as usize;
+            for step in 1..steps {
+                let t = step as f64 / steps as f64;
+                let angle = start_angle + delta * t;
+                result.push(Point::new(vertex.x + radius * angle.cos(), vertex.y + radius * angle.sin()));
+            }
+            result.push(cur.0);
+        } else if let Some(p) = line_intersection(prev.0, prev.1, cur.0, cur.1) {
+            result.push(p);
+        } else {
+            result.push(prev.1);
+            result.push(cur.0);
+        }
+    }
+    if let Some(&first) = result.first() {
+        result.push(first);
+    }
+    result
+}
+
+/// Arithmetic-mean centroid of a closed ring (first point == last point),
+/// used only as a coarse erosion-limit estimate in [`erosion_limit`].
+fn ring_centroid(points: &[Point]) -> Point {
+    let open = &points[..points.len().saturating_sub(1)];
+    let n = (open.len().max(1)) as f64;
+    let (sx, sy) = open.iter().fold((0.0, 0.0), |(sx, sy), p| (sx + p.x, sy + p.y));
+    Point::new(sx / n, sy / n)
+}
+
+/// Coarse bound on how far a ring can be eroded before the offset invert
+/// through the ring's own interior: the distance from the centroid to the
+/// nearest edge. Exact when the centroid is the centre of the ring's largest
+/// inscribed circle (e.g. rectangles, regular polygons); for other convex
+/// rings it underestimates that limit, so a ring eroded by slightly less
+/// than its inradius may already be dropped, and for non-convex ones it is
+/// only an estimate. It is enough to catch an erosion distance large enough to
+/// push the offset past the opposite side of the ring (which a bare
+/// before/after area-sign comparison misses whenever the inverted ring
+/// happens to come out with the same signed area, e.g. a symmetric shape
+/// eroded through its own center).
+fn erosion_limit(points: &[Point]) -> f64 {
+    let centroid = ring_centroid(points);
+    points
+        .windows(2)
+        .map(|w| perpendicular_distance(centroid, w[0], w[1]))
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Offsets every ring of `poly` by `dist` (see [`offset_ring`]), dropping
+/// rings that collapse or invert under erosion. A hole (`Inner` ring)'s
+/// effective offset is the negation of `dist`: growing the solid region
+/// erodes a hole's own boundary, and shrinking the solid grows it. Returns
+/// `None` if every ring collapses (`shapefile::Polygon::with_rings` panics
+/// on an empty ring list, so that case can't be represented as a `Polygon`).
+pub fn buffer_polygon(poly: &Polygon, dist: f64) -> Option<Polygon> {
+    let rings = poly
+        .rings()
+        .iter()
+        .filter_map(|ring| {
+            let (points, rebuild, ring_dist): (&Vec<Point>, fn(Vec<Point>) -> PolygonRing<Point>, f64) =
+                match ring {
+                    PolygonRing::Outer(pts) => (pts, PolygonRing::Outer, dist),
+                    PolygonRing::Inner(pts) => (pts, PolygonRing::Inner, -dist),
+                };
+            let offset = offset_ring(points, ring_dist);
+            let collapsed = ring_dist < 0.0 && ring_dist.abs() >= erosion_limit(points);
+            if offset.len() < 4 || collapsed {
+                return None;
+            }
+            Some(rebuild(offset))
+        })
+        .collect::<Vec<PolygonRing<Point>>>();
+    if rings.is_empty() {
+        return None;
+    }
+    Some(Polygon::with_rings(rings))
+}
+
+/// Converts every part of a (M/Z) polyline to plain `Point`s, dropping the
+/// M/Z coordinate the same way [`polymz2poly`] does for polygons.
+fn polyline_parts_to_points<PointType>(line: &GenericPolyline<PointType>) -> Vec<Vec<Point>>
+where
+    PointType: HasXY + Copy,
+{
+    line.parts()
+        .iter()
+        .map(|part| part.iter().map(|&p| Point::new(p.x(), p.y())).collect())
+        .collect()
 }
 
-fn load_polygons<P: AsRef<Path>>(shapefile: P) -> Result<Vec<Polygon>> {
+/// Points along the arc of `center`-centered circle of `radius` from `from`
+/// to `to`, picking whichever of the two possible sweep directions bulges
+/// towards `bulge_dir` (used for the round caps at a polyline's endpoints,
+/// where a half-circle is ambiguous without a preferred direction).
+fn arc_points(center: Point, from: Point, to: Point, radius: f64, bulge_dir: (f64, f64)) -> Vec<Point> {
+    let start_angle = (from.y - center.y).atan2(from.x - center.x);
+    let raw_end_angle = (to.y - center.y).atan2(to.x - center.x);
+    let mut delta = raw_end_angle - start_angle;
+    while delta <= -PI {
+        delta += 2.0 * PI;
+    }
+    while delta > PI {
+        delta -= 2.0 * PI;
+    }
+    let alt_delta = if delta >= 0.0 { delta - 2.0 * PI } else { delta + 2.0 * PI };
+    let bulge_score = |d: f64| {
+        let mid_angle = start_angle + d / 2.0;
+        mid_angle.cos() * bulge_dir.0 + mid_angle.sin() * bulge_dir.1
+    };
+    let chosen = if bulge_score(delta) >= bulge_score(alt_delta) { delta } else { alt_delta };
+
+    let steps = ((BUFFER_ARC_SEGMENTS_PER_QUADRANT as f64) * (chosen.abs() / (PI / 2.0)))
+        .ceil()
+        .max(1.0) as usize;
+    (1..steps)
+        .map(|step| {
+            let t = step as f64 / steps as f64;
+            let angle = start_angle + chosen * t;
+            Point::new(center.x + radius * angle.cos(), center.y + radius * angle.sin())
+        })
+        .collect()
+}
+
+/// Buffers an open polyline to both sides by `half_width`, producing a
+/// single closed corridor polygon with round caps at the two endpoints
+/// (the polyline equivalent of [`buffer_polygon`]).
+fn corridor_from_polyline(points: &[Point], half_width: f64) -> Option<Polygon> {
+    if points.len() < 2 || half_width <= 0.0 {
+        return None;
+    }
+    let n = points.len();
+    let edge_normal = |p1: Point, p2: Point| rotate_minus90(normalize((p2.x - p1.x, p2.y - p1.y)));
+    let normals: Vec<(f64, f64)> = (0..n)
+        .map(|i| {
+            let mut sum = (0.0, 0.0);
+            if i > 0 {
+                let en = edge_normal(points[i - 1], points[i]);
+                sum = (sum.0 + en.0, sum.1 + en.1);
+            }
+            if i + 1 < n {
+                let en = edge_normal(points[i], points[i + 1]);
+                sum = (sum.0 + en.0, sum.1 + en.1);
+            }
+            normalize(sum)
+        })
+        .collect();
+
+    let left: Vec<Point> = points
+        .iter()
+        .zip(&normals)
+        .map(|(&p, &nv)| Point::new(p.x + nv.0 * half_width, p.y + nv.1 * half_width))
+        .collect();
+    let right: Vec<Point> = points
+        .iter()
+        .zip(&normals)
+        .map(|(&p, &nv)| Point::new(p.x - nv.0 * half_width, p.y - nv.1 * half_width))
+        .collect();
+
+    let end_dir = normalize((points[n - 1].x - points[n - 2].x, points[n - 1].y - points[n - 2].y));
+    let start_dir = normalize((points[0].x - points[1].x, points[0].y - points[1].y));
+
+    let mut ring = Vec::with_capacity(2 * n + 2 * BUFFER_ARC_SEGMENTS_PER_QUADRANT + 1);
+    ring.extend(left.iter().copied());
+    ring.extend(arc_points(points[n - 1], left[n - 1], right[n - 1], half_width, end_dir));
+    ring.extend(right.iter().rev().copied());
+    ring.extend(arc_points(points[0], right[0], left[0], half_width, start_dir));
+    if let Some(&first) = ring.first() {
+        ring.push(first);
+    }
+    Some(Polygon::with_rings(vec![PolygonRing::Outer(ring)]))
+}
+
+/// Above this vertex count, seeding Douglas-Peucker with the true farthest
+/// pair of vertices (an O(n^2) search) is skipped in favour of a cheap
+/// opposite-side guess, so simplifying a very dense ring stays fast.
+const FARTHEST_PAIR_BRUTE_FORCE_LIMIT: usize = 2_000;
+
+fn dist2(a: Point, b: Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    dx * dx + dy * dy
+}
+
+fn perpendicular_distance(p: Point, a: Point, b: Point) -> f64 {
+    let len2 = dist2(a, b);
+    if len2 < f64::EPSILON {
+        return dist2(p, a).sqrt();
+    }
+    ((b.y - a.y) * (p.x - a.x) - (b.x - a.x) * (p.y - a.y)).abs() / len2.sqrt()
+}
+
+/// Classic Douglas-Peucker simplification of an open polyline: keeps `A`
+/// and `B`, recursing on the farthest-from-`AB` vertex when it exceeds
+/// `tolerance`, otherwise discarding everything in between.
+fn douglas_peucker(points: &[Point], tolerance: f64) -> Vec<Point> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let (a, b) = (points[0], points[points.len() - 1]);
+    let mut farthest = (0usize, 0.0);
+    for (i, &p) in points.iter().enumerate().take(points.len() - 1).skip(1) {
+        let d = perpendicular_distance(p, a, b);
+        if d > farthest.1 {
+            farthest = (i, d);
+        }
+    }
+    if farthest.1 > tolerance {
+        let mut left = douglas_peucker(&points[..=farthest.0], tolerance);
+        let right = douglas_peucker(&points[farthest.0..], tolerance);
+        left.pop();
+        left.extend(right);
+        left
+    } else {
+        vec![a, b]
+    }
+}
+
+/// Pair of indices to seed the Douglas-Peucker split for a closed ring;
+/// splitting at its two mutually farthest vertices keeps the ring from
+/// collapsing into a line the way an arbitrary endpoint split would.
+fn farthest_pair(points: &[Point]) -> (usize, usize) {
+    let n = points.len();
+    if n > FARTHEST_PAIR_BRUTE_FORCE_LIMIT {
+        return (0, n / 2);
+    }
+    let mut best = (0usize, (n - 1).min(1), 0.0);
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let d = dist2(points[i], points[j]);
+            if d > best.2 {
+                best = (i, j, d);
+            }
+        }
+    }
+    (best.0, best.1)
+}
+
+/// Simplifies a closed ring (first point == last point) with Douglas-Peucker,
+/// bounding the geometric error by `tolerance`.
+fn simplify_ring(points: &[Point], tolerance: f64) -> Vec<Point> {
+    if points.len() < 5 || tolerance <= 0.0 {
+        return points.to_vec();
+    }
+    let open = &points[..points.len() - 1];
+    let (i, j) = farthest_pair(open);
+    let chain_a: Vec<Point> = open[i..=j].to_vec();
+    let mut chain_b: Vec<Point> = open[j..].to_vec();
+    chain_b.extend_from_slice(&open[..=i]);
+
+    let mut simplified = douglas_peucker(&chain_a, tolerance);
+    simplified.pop();
+    simplified.extend(douglas_peucker(&chain_b, tolerance));
+    if simplified.first() != simplified.last() {
+        if let Some(&first) = simplified.first() {
+            simplified.push(first);
+        }
+    }
+    simplified
+}
+
+/// Simplifies every ring of `poly`, preserving the outer/inner tag.
+fn simplify_polygon(poly: &Polygon, tolerance: f64) -> Polygon {
+    let rings = poly
+        .rings()
+        .iter()
+        .map(|ring| match ring {
+            PolygonRing::Outer(pts) => PolygonRing::Outer(simplify_ring(pts, tolerance)),
+            PolygonRing::Inner(pts) => PolygonRing::Inner(simplify_ring(pts, tolerance)),
+        })
+        .collect::<Vec<PolygonRing<Point>>>();
+    Polygon::with_rings(rings)
+}
+
+/// Reprojects every vertex of `poly` from `from` to `to`, preserving the
+/// outer/inner nature of each ring.
+fn reproject_polygon(poly: &Polygon, from: Crs, to: Crs) -> Polygon {
+    let rings = poly
+        .rings()
+        .iter()
+        .map(|ring| match ring {
+            PolygonRing::Outer(pts) => {
+                PolygonRing::Outer(pts.iter().map(|&p| crs::transform_point(p, from, to)).collect())
+            }
+            PolygonRing::Inner(pts) => {
+                PolygonRing::Inner(pts.iter().map(|&p| crs::transform_point(p, from, to)).collect())
+            }
+        })
+        .collect::<Vec<PolygonRing<Point>>>();
+    Polygon::with_rings(rings)
+}
+
+/// Reprojects a set of raw vertices (e.g. a polyline's points, before a
+/// corridor polygon is built from them) from `from` to `to`, when both are
+/// known and differ; see [`reproject_polygon`] for the whole-polygon case.
+fn reproject_points(points: Vec<Point>, from: Option<Crs>, to: Option<Crs>) -> Vec<Point> {
+    match (from, to) {
+        (Some(from), Some(to)) if from != to => points
+            .into_iter()
+            .map(|p| crs::transform_point(p, from, to))
+            .collect(),
+        _ => points,
+    }
+}
+
+fn load_polygons<P: AsRef<Path>>(
+    shapefile: P,
+    shapefile_crs: Option<Crs>,
+    las_crs: Option<Crs>,
+    buffer: f64,
+    corridor: f64,
+    simplify: f64,
+) -> Result<Vec<Polygon>> {
     let mut reader = shapefile::ShapeReader::from_path(shapefile.as_ref()).with_context(|| {
         format!(
             "Cannot open shapefile \"{}\"",
             shapefile.as_ref().to_string_lossy()
         )
     })?;
-    Ok(reader
-        .iter_shapes()
-        .map_while(|shape| shape.ok())
-        .filter_map(|s| match s {
-            Shape::Polygon(poly) => Some(poly.clone()),
-            Shape::PolygonM(poly) => Some(polymz2poly(&poly)),
-            Shape::PolygonZ(poly) => Some(polymz2poly(&poly)),
-            _ => None,
-        })
-        .collect())
+
+    // Polygon shapes are reprojected as a whole below (points vastly
+    // outnumber polygon vertices, so it is cheaper to reproject the
+    // polygons once here rather than every point). Polyline shapes need
+    // their raw vertices reprojected into the LAS CRS *before* the corridor
+    // is built from them, since `--corridor <w>` is a metric half-width in
+    // LAS CRS units and the corridor geometry itself is never reprojected.
+    let mut shape_polygons: Vec<Polygon> = Vec::new();
+    let mut corridor_polygons: Vec<Polygon> = Vec::new();
+    for s in reader.iter_shapes().map_while(|shape| shape.ok()) {
+        match s {
+            Shape::Polygon(poly) => shape_polygons.push(poly.clone()),
+            Shape::PolygonM(poly) => shape_polygons.push(polymz2poly(&poly)),
+            Shape::PolygonZ(poly) => shape_polygons.push(polymz2poly(&poly)),
+            Shape::Polyline(line) if corridor > 0.0 => corridor_polygons.extend(
+                polyline_parts_to_points(&line)
+                    .into_iter()
+                    .map(|pts| reproject_points(pts, shapefile_crs, las_crs))
+                    .filter_map(|pts| corridor_from_polyline(&pts, corridor)),
+            ),
+            Shape::PolylineM(line) if corridor > 0.0 => corridor_polygons.extend(
+                polyline_parts_to_points(&line)
+                    .into_iter()
+                    .map(|pts| reproject_points(pts, shapefile_crs, las_crs))
+                    .filter_map(|pts| corridor_from_polyline(&pts, corridor)),
+            ),
+            Shape::PolylineZ(line) if corridor > 0.0 => corridor_polygons.extend(
+                polyline_parts_to_points(&line)
+                    .into_iter()
+                    .map(|pts| reproject_points(pts, shapefile_crs, las_crs))
+                    .filter_map(|pts| corridor_from_polyline(&pts, corridor)),
+            ),
+            _ => {}
+        }
+    }
+
+    let shape_polygons = match (shapefile_crs, las_crs) {
+        (Some(from), Some(to)) if from != to => shape_polygons
+            .iter()
+            .map(|poly| reproject_polygon(poly, from, to))
+            .collect(),
+        _ => shape_polygons,
+    };
+    let polygons: Vec<Polygon> = shape_polygons.into_iter().chain(corridor_polygons).collect();
+
+    let polygons: Vec<Polygon> = if buffer != 0.0 {
+        polygons
+            .iter()
+            // An erosion that fully collapses every ring of a polygon drops
+            // it (see `buffer_polygon`); such a polygon can never contain a
+            // point, so it must not linger on to inflate `total_polygons`
+            // for Strategy::Intersection/AtLeast below.
+            .filter_map(|poly| buffer_polygon(poly, buffer))
+            .collect()
+    } else {
+        polygons
+    };
+
+    Ok(if simplify > 0.0 {
+        polygons
+            .iter()
+            .map(|poly| simplify_polygon(poly, simplify))
+            .collect()
+    } else {
+        polygons
+    })
 }
 
 fn filter_fn(
     strategy: Strategy,
-    polygons: &Vec<Polygon>,
+    index: &PolygonIndex,
     point: &lasPoint,
     external: bool,
     offsets: &[f64; 2],
 ) -> bool {
     let point = Point::new(point.x, point.y);
-    let op = |poly| is_point_in_poly(&point, poly, offsets) != external;
-    match strategy {
-        Strategy::Union => polygons.iter().any(op),
-        Strategy::Intersection => polygons.iter().all(op),
-    }
+    // Only polygons whose bbox contains the point can possibly contain it,
+    // so the winding-number test only ever runs on those.
+    let inside_count = index
+        .candidates(point.x, point.y)
+        .into_iter()
+        .filter(|&i| winding_number_over_edges(&point, index.edges_near(i, point.y), offsets) != 0)
+        .count();
+    // `external` inverts the final membership test, not each per-polygon one.
+    strategy_matches(strategy, inside_count, index.len()) != external
 }
 
 fn print_info<P: AsRef<Path> + std::fmt::Display>(
@@ -176,15 +668,51 @@ fn create_progress_bar(total: u64) -> Result<ProgressBar> {
     Ok(pb)
 }
 
+/// Secondary knobs for [`clip`]: CRS overrides, pre-clip geometry
+/// transforms, and run performance, all of which are optional and
+/// independent of the mandatory lasfile/shapefile/outfile/strategy
+/// parameters. Bundled into one struct to keep `clip` under clippy's
+/// too-many-arguments threshold.
+pub struct ClipOptions {
+    /// Number of threads. If 0, all available cores are used.
+    pub nthreads: usize,
+    /// Number of points read per chunk while processing.
+    pub read_chunk: u64,
+    /// Override the CRS of the shapefile (e.g. "EPSG:4326" or "UTM:33N").
+    /// By default it is read from the sidecar ".prj" file.
+    pub shapefile_crs: Option<String>,
+    /// Override the CRS of the las file (e.g. "EPSG:32633" or "UTM:33N").
+    /// By default it is read from the header's GeoTIFF VLR.
+    pub las_crs: Option<String>,
+    /// Grow (positive) or shrink (negative) every loaded polygon by this
+    /// distance, in the units of the las CRS, before clipping.
+    pub buffer: f64,
+    /// Treat polyline shapes in the shapefile as corridors: buffer each
+    /// line to both sides by this half-width to build a clip polygon.
+    pub corridor: f64,
+    /// Simplify every polygon ring with Douglas-Peucker before clipping,
+    /// discarding vertices within this distance of the simplified line.
+    pub simplify: f64,
+}
+
 pub fn clip<P: AsRef<Path> + std::fmt::Display>(
     lasfile: P,
     shapefile: P,
     outfile: P,
     strategy: Strategy,
     external: bool,
-    nthreads: usize,
-    read_chunk: u64,
+    options: ClipOptions,
 ) -> Result<()> {
+    let ClipOptions {
+        nthreads,
+        read_chunk,
+        shapefile_crs,
+        las_crs,
+        buffer,
+        corridor,
+        simplify,
+    } = options;
+
     if nthreads > 0 {
         rayon::ThreadPoolBuilder::new()
             .num_threads(nthreads)
@@ -213,8 +741,23 @@ pub fn clip<P: AsRef<Path> + std::fmt::Display>(
         }
     }
 
+    // Open input las file first so its header is available for CRS detection
+    let mut reader = Reader::from_path(lasfile.as_ref())
+        .with_context(|| format!("Cannot open las file \"{lasfile}\""))?;
+
+    // Resolve the CRS of each input, falling back from the CLI override to
+    // what can be read from the las header / shapefile ".prj" sidecar.
+    let las_crs = las_crs
+        .map(|s| Crs::parse(&s))
+        .transpose()?
+        .or_else(|| crs::las_crs_from_header(reader.header()));
+    let shapefile_crs = shapefile_crs
+        .map(|s| Crs::parse(&s))
+        .transpose()?
+        .or_else(|| crs::shapefile_crs_from_prj(&shapefile));
+
     // Getting polygons
-    let polygons = load_polygons(&shapefile)?;
+    let polygons = load_polygons(&shapefile, shapefile_crs, las_crs, buffer, corridor, simplify)?;
     println!(
         "[1/2] {} polygon{} loaded from \"{}\".",
         polygons.len(),
@@ -222,9 +765,11 @@ pub fn clip<P: AsRef<Path> + std::fmt::Display>(
         shapefile.as_ref().to_string_lossy()
     );
 
-    // Open input and output las files
-    let mut reader = Reader::from_path(lasfile.as_ref())
-        .with_context(|| format!("Cannot open las file \"{lasfile}\""))?;
+    // Build the spatial index once so per-point containment tests only
+    // touch the polygons/edges near that point instead of scanning everything.
+    let index = PolygonIndex::build(&polygons);
+
+    // Open output las file
     let out_header: Header = reader.header().clone();
     let mut writer = Writer::from_path(outfile.as_ref(), out_header)
         .with_context(|| format!("Cannot open las output file \"{outfile}\""))?;
@@ -248,7 +793,7 @@ pub fn clip<P: AsRef<Path> + std::fmt::Display>(
         let points = reader.read_n(read_chunk.min(points_total - points_processes))?;
         let contained: Vec<&las::Point> = points
             .par_iter()
-            .filter(|&pnt| filter_fn(strategy, &polygons, pnt, external, &offsets))
+            .filter(|&pnt| filter_fn(strategy, &index, pnt, external, &offsets))
             .collect();
 
         for &p in contained.iter() {
@@ -272,3 +817,131 @@ pub fn clip<P: AsRef<Path> + std::fmt::Display>(
     );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(half_side: f64) -> Polygon {
+        let ring = vec![
+            Point::new(-half_side, -half_side),
+            Point::new(half_side, -half_side),
+            Point::new(half_side, half_side),
+            Point::new(-half_side, half_side),
+            Point::new(-half_side, -half_side),
+        ];
+        Polygon::with_rings(vec![PolygonRing::Outer(ring)])
+    }
+
+    fn ring_area(poly: &Polygon) -> f64 {
+        match &poly.rings()[0] {
+            PolygonRing::Outer(pts) | PolygonRing::Inner(pts) => signed_area(pts).abs(),
+        }
+    }
+
+    #[test]
+    fn buffer_polygon_dilates_a_square_outward() {
+        let poly = square(10.0);
+        let dilated = buffer_polygon(&poly, 2.0).expect("dilation keeps the ring");
+        // A square with half-side 10 grown by 2 on every side has area
+        // roughly 24*24 (exactly that, modulo the round joins bulging out
+        // slightly further at the corners), so it must be bigger than the
+        // 20*20 original.
+        assert!(ring_area(&dilated) > ring_area(&poly));
+    }
+
+    #[test]
+    fn buffer_polygon_erodes_a_square_inward() {
+        let poly = square(10.0);
+        let eroded = buffer_polygon(&poly, -2.0).expect("erosion within the inradius keeps the ring");
+        assert!(ring_area(&eroded) < ring_area(&poly));
+    }
+
+    #[test]
+    fn buffer_polygon_drops_a_ring_fully_eroded_away() {
+        let poly = square(10.0);
+        // Eroding a half-side-10 square by 20 pushes every edge past the
+        // opposite side (and, for this symmetric shape, the naive offset
+        // ring comes back out with the very same signed area as the
+        // original), so this only collapses correctly if detected via the
+        // erosion-limit bound rather than an area-sign comparison.
+        assert!(buffer_polygon(&poly, -20.0).is_none());
+    }
+
+    #[test]
+    fn buffer_polygon_erodes_a_hole_when_dilating_the_solid() {
+        let outer = vec![
+            Point::new(-10.0, -10.0),
+            Point::new(10.0, -10.0),
+            Point::new(10.0, 10.0),
+            Point::new(-10.0, 10.0),
+            Point::new(-10.0, -10.0),
+        ];
+        let hole = vec![
+            Point::new(-2.0, -2.0),
+            Point::new(-2.0, 2.0),
+            Point::new(2.0, 2.0),
+            Point::new(2.0, -2.0),
+            Point::new(-2.0, -2.0),
+        ];
+        let poly = Polygon::with_rings(vec![PolygonRing::Outer(outer), PolygonRing::Inner(hole)]);
+        let dilated = buffer_polygon(&poly, 1.0).expect("dilation keeps both rings");
+        assert_eq!(dilated.rings().len(), 2);
+        let hole_area = match &dilated.rings()[1] {
+            PolygonRing::Outer(pts) | PolygonRing::Inner(pts) => signed_area(pts).abs(),
+        };
+        // The 4x4 hole must shrink (toward ~2x2=4), not grow, when the
+        // surrounding solid is dilated.
+        assert!(hole_area < 16.0);
+    }
+
+    #[test]
+    fn corridor_from_polyline_has_roughly_2x_half_width_as_its_width() {
+        let points = vec![Point::new(0.0, 0.0), Point::new(100.0, 0.0)];
+        let corridor = corridor_from_polyline(&points, 5.0).expect("corridor for a 2-point line");
+        let (min, max) = corridor.rings()[0]
+            .points()
+            .iter()
+            .fold(([f64::INFINITY; 2], [f64::NEG_INFINITY; 2]), |(mut min, mut max), p| {
+                min[0] = min[0].min(p.x);
+                min[1] = min[1].min(p.y);
+                max[0] = max[0].max(p.x);
+                max[1] = max[1].max(p.y);
+                (min, max)
+            });
+        assert!((max[1] - min[1] - 10.0).abs() < 1e-6);
+        // The round caps extend the bbox a half-width past each endpoint.
+        assert!(min[0] < -4.9 && max[0] > 104.9);
+    }
+
+    #[test]
+    fn douglas_peucker_collapses_a_near_straight_line() {
+        let points = vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.01),
+            Point::new(2.0, -0.01),
+            Point::new(3.0, 0.0),
+        ];
+        let simplified = douglas_peucker(&points, 1.0);
+        assert_eq!(simplified, vec![points[0], points[3]]);
+    }
+
+    #[test]
+    fn douglas_peucker_keeps_a_point_that_exceeds_tolerance() {
+        let points = vec![Point::new(0.0, 0.0), Point::new(1.0, 10.0), Point::new(2.0, 0.0)];
+        let simplified = douglas_peucker(&points, 1.0);
+        assert_eq!(simplified, points);
+    }
+
+    #[test]
+    fn strategy_matches_truth_table() {
+        assert!(strategy_matches(Strategy::Union, 1, 3));
+        assert!(!strategy_matches(Strategy::Union, 0, 3));
+        assert!(strategy_matches(Strategy::Intersection, 3, 3));
+        assert!(!strategy_matches(Strategy::Intersection, 2, 3));
+        assert!(strategy_matches(Strategy::Xor, 1, 3));
+        assert!(!strategy_matches(Strategy::Xor, 2, 3));
+        assert!(strategy_matches(Strategy::AtLeast(2), 2, 3));
+        assert!(!strategy_matches(Strategy::AtLeast(2), 1, 3));
+    }
+}
@@ -0,0 +1,328 @@
+//! Minimal coordinate-reference-system support used to reproject shapefile
+//! polygons into the CRS of the LAS point cloud before clipping.
+//!
+//! Only the two families of CRS that show up in practice for airborne LiDAR
+//! work are supported: geographic (lat/lon on an ellipsoid) and
+//! Transverse-Mercator/UTM. Everything is implemented from the standard
+//! Snyder forward/inverse series so no external projection library is
+//! required.
+use anyhow::{bail, Context, Result};
+use las::Header;
+use shapefile::Point;
+use std::path::Path;
+
+/// WGS84 semi-major axis (metres).
+const WGS84_A: f64 = 6_378_137.0;
+/// WGS84 flattening.
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+/// UTM central scale factor.
+const UTM_K0: f64 = 0.9996;
+/// UTM false easting (metres).
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+/// UTM false northing applied in the southern hemisphere (metres).
+const UTM_FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+
+/// EPSG code for geographic WGS84 (lat/lon).
+const EPSG_WGS84_GEOGRAPHIC: u16 = 4326;
+/// First EPSG code of the WGS84 / UTM north zone block (32601..32660).
+const EPSG_UTM_NORTH_BASE: u16 = 32600;
+/// First EPSG code of the WGS84 / UTM south zone block (32701..32760).
+const EPSG_UTM_SOUTH_BASE: u16 = 32700;
+
+/// A coordinate reference system recognized by the reprojection subsystem.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Crs {
+    /// Geographic lat/lon in degrees on the WGS84 ellipsoid.
+    Geographic,
+    /// Transverse-Mercator / UTM on the WGS84 ellipsoid.
+    Utm { zone: u8, north: bool },
+}
+
+impl Crs {
+    /// Parses a CRS from a user-provided override string such as
+    /// `"EPSG:4326"`, `"EPSG:32633"` or `"UTM:33N"`.
+    pub fn parse(s: &str) -> Result<Crs> {
+        let s = s.trim();
+        if let Some(epsg) = s.strip_prefix("EPSG:").or_else(|| s.strip_prefix("epsg:")) {
+            let code: u16 = epsg
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid EPSG code \"{epsg}\""))?;
+            return Crs::from_epsg(code)
+                .with_context(|| format!("Unsupported EPSG code {code}"));
+        }
+        if let Some(utm) = s.strip_prefix("UTM:").or_else(|| s.strip_prefix("utm:")) {
+            return parse_utm_zone(utm.trim());
+        }
+        bail!("Cannot parse CRS override \"{s}\" (expected \"EPSG:<code>\" or \"UTM:<zone><N|S>\")")
+    }
+
+    /// Maps a handful of well-known EPSG codes (geographic WGS84 and the
+    /// WGS84 UTM north/south zone blocks) to a [`Crs`].
+    pub fn from_epsg(code: u16) -> Option<Crs> {
+        if code == EPSG_WGS84_GEOGRAPHIC {
+            return Some(Crs::Geographic);
+        }
+        if code > EPSG_UTM_NORTH_BASE && code <= EPSG_UTM_NORTH_BASE + 60 {
+            return Some(Crs::Utm {
+                zone: (code - EPSG_UTM_NORTH_BASE) as u8,
+                north: true,
+            });
+        }
+        if code > EPSG_UTM_SOUTH_BASE && code <= EPSG_UTM_SOUTH_BASE + 60 {
+            return Some(Crs::Utm {
+                zone: (code - EPSG_UTM_SOUTH_BASE) as u8,
+                north: false,
+            });
+        }
+        None
+    }
+}
+
+fn parse_utm_zone(s: &str) -> Result<Crs> {
+    let (zone_str, hemi) = s.split_at(s.len().saturating_sub(1));
+    let zone: u8 = zone_str
+        .parse()
+        .with_context(|| format!("Invalid UTM zone \"{s}\""))?;
+    let north = match hemi.to_uppercase().as_str() {
+        "N" => true,
+        "S" => false,
+        other => bail!("Invalid UTM hemisphere \"{other}\" (expected N or S)"),
+    };
+    if zone == 0 || zone > 60 {
+        bail!("UTM zone {zone} out of range (1..=60)");
+    }
+    Ok(Crs::Utm { zone, north })
+}
+
+/// Reads the CRS embedded in a LAS header's GeoTIFF VLR, if present.
+///
+/// Only the `ProjectedCSTypeGeoKey` (3072) and `GeographicTypeGeoKey` (2048)
+/// entries of the `GeoKeyDirectoryTag` (record id 34735, user id
+/// `"LASF_Projection"`) are inspected, which is enough to recover the EPSG
+/// code for the common UTM-on-WGS84 and geographic-WGS84 cases.
+pub fn las_crs_from_header(header: &Header) -> Option<Crs> {
+    let vlr = header
+        .vlrs()
+        .iter()
+        .find(|vlr| vlr.user_id == "LASF_Projection" && vlr.record_id == 34735)?;
+    geo_key_directory_epsg(&vlr.data).and_then(Crs::from_epsg)
+}
+
+/// Parses a `GeoKeyDirectoryTag` looking for a projected or geographic EPSG
+/// code. The directory is an array of `u16` quads `(key_id, tiff_tag_location,
+/// count, value_offset)`; an EPSG code is stored directly in `value_offset`
+/// when `tiff_tag_location == 0`.
+fn geo_key_directory_epsg(data: &[u8]) -> Option<u16> {
+    const PROJECTED_CS_TYPE_GEO_KEY: u16 = 3072;
+    const GEOGRAPHIC_TYPE_GEO_KEY: u16 = 2048;
+
+    let mut values = data.chunks_exact(2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+    let _key_directory_version = values.next()?;
+    let _key_revision = values.next()?;
+    let _minor_revision = values.next()?;
+    let num_keys = values.next()?;
+
+    let mut projected = None;
+    let mut geographic = None;
+    for _ in 0..num_keys {
+        let key_id = values.next()?;
+        let tiff_tag_location = values.next()?;
+        let _count = values.next()?;
+        let value_offset = values.next()?;
+        if tiff_tag_location != 0 {
+            continue;
+        }
+        match key_id {
+            k if k == PROJECTED_CS_TYPE_GEO_KEY => projected = Some(value_offset),
+            k if k == GEOGRAPHIC_TYPE_GEO_KEY => geographic = Some(value_offset),
+            _ => {}
+        }
+    }
+    projected.filter(|&v| v != 0 && v != 32767).or(geographic)
+}
+
+/// Reads the CRS described by a shapefile's sidecar `.prj` file, if one
+/// exists next to `shapefile_path`.
+///
+/// The WKT grammar used by `.prj` files is not fully parsed; instead we look
+/// for the handful of substrings that distinguish a geographic CRS from a
+/// UTM/Transverse-Mercator one, which covers the overwhelming majority of
+/// `.prj` files produced by GIS tooling.
+pub fn shapefile_crs_from_prj<P: AsRef<Path>>(shapefile_path: P) -> Option<Crs> {
+    let prj_path = shapefile_path.as_ref().with_extension("prj");
+    let wkt = std::fs::read_to_string(prj_path).ok()?;
+    crs_from_wkt(&wkt)
+}
+
+fn crs_from_wkt(wkt: &str) -> Option<Crs> {
+    if let Some(pos) = wkt.find("UTM_Zone_").or_else(|| wkt.find("UTM zone ")) {
+        let rest = &wkt[pos..];
+        let digit_start = rest.find(|c: char| c.is_ascii_digit())?;
+        let digits: String = rest[digit_start..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        let zone: u8 = digits.parse().ok()?;
+        // Only the single character right after the zone digits denotes the
+        // hemisphere (e.g. "UTM_Zone_33S"); scanning further into the WKT
+        // would match unrelated letters in the projection/datum name.
+        let hemisphere = rest[digit_start + digits.len()..].chars().next();
+        let north = !matches!(hemisphere, Some('S') | Some('s'));
+        return Some(Crs::Utm { zone, north });
+    }
+    if wkt.starts_with("GEOGCS") || wkt.contains("\"GEOGCS\"") {
+        return Some(Crs::Geographic);
+    }
+    None
+}
+
+/// Converts a geographic point (`x` = longitude, `y` = latitude, in degrees)
+/// to UTM easting/northing (metres) in the given zone.
+fn geographic_to_utm(lon_deg: f64, lat_deg: f64, zone: u8, north: bool) -> (f64, f64) {
+    let a = WGS84_A;
+    let f = WGS84_F;
+    let e2 = f * (2.0 - f);
+    let ep2 = e2 / (1.0 - e2);
+
+    let lat = lat_deg.to_radians();
+    let lon = lon_deg.to_radians();
+    let lon0 = (zone as f64 * 6.0 - 183.0).to_radians();
+
+    let sin_lat = lat.sin();
+    let cos_lat = lat.cos();
+    let tan_lat = lat.tan();
+
+    let n = a / (1.0 - e2 * sin_lat * sin_lat).sqrt();
+    let t = tan_lat * tan_lat;
+    let c = ep2 * cos_lat * cos_lat;
+    let ll = lon - lon0;
+
+    let m = a
+        * ((1.0 - e2 / 4.0 - 3.0 * e2 * e2 / 64.0 - 5.0 * e2 * e2 * e2 / 256.0) * lat
+            - (3.0 * e2 / 8.0 + 3.0 * e2 * e2 / 32.0 + 45.0 * e2 * e2 * e2 / 1024.0) * (2.0 * lat).sin()
+            + (15.0 * e2 * e2 / 256.0 + 45.0 * e2 * e2 * e2 / 1024.0) * (4.0 * lat).sin()
+            - (35.0 * e2 * e2 * e2 / 3072.0) * (6.0 * lat).sin());
+
+    let a_ = ll * cos_lat;
+    let x = UTM_K0
+        * n
+        * (a_ + (1.0 - t + c) * a_.powi(3) / 6.0
+            + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a_.powi(5) / 120.0)
+        + UTM_FALSE_EASTING;
+    let mut y = UTM_K0
+        * (m
+            + n * tan_lat
+                * (a_.powi(2) / 2.0
+                    + (5.0 - t + 9.0 * c + 4.0 * c * c) * a_.powi(4) / 24.0
+                    + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a_.powi(6) / 720.0));
+    if !north {
+        y += UTM_FALSE_NORTHING_SOUTH;
+    }
+    (x, y)
+}
+
+/// Converts a UTM easting/northing (metres) in the given zone back to
+/// geographic longitude/latitude (degrees).
+fn utm_to_geographic(easting: f64, northing: f64, zone: u8, north: bool) -> (f64, f64) {
+    let a = WGS84_A;
+    let f = WGS84_F;
+    let e2 = f * (2.0 - f);
+    let ep2 = e2 / (1.0 - e2);
+    let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+    let lon0 = (zone as f64 * 6.0 - 183.0).to_radians();
+
+    let x = easting - UTM_FALSE_EASTING;
+    let y = if north {
+        northing
+    } else {
+        northing - UTM_FALSE_NORTHING_SOUTH
+    };
+
+    let m = y / UTM_K0;
+    let mu = m / (a * (1.0 - e2 / 4.0 - 3.0 * e2 * e2 / 64.0 - 5.0 * e2 * e2 * e2 / 256.0));
+
+    let phi1 = mu
+        + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+        + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+        + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+        + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+    let sin_phi1 = phi1.sin();
+    let cos_phi1 = phi1.cos();
+    let tan_phi1 = phi1.tan();
+
+    let c1 = ep2 * cos_phi1 * cos_phi1;
+    let t1 = tan_phi1 * tan_phi1;
+    let n1 = a / (1.0 - e2 * sin_phi1 * sin_phi1).sqrt();
+    let r1 = a * (1.0 - e2) / (1.0 - e2 * sin_phi1 * sin_phi1).powf(1.5);
+    let d = x / (n1 * UTM_K0);
+
+    let lat = phi1
+        - (n1 * tan_phi1 / r1)
+            * (d * d / 2.0
+                - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1 * c1 - 9.0 * ep2) * d.powi(4) / 24.0
+                + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1 * t1 - 252.0 * ep2 - 3.0 * c1 * c1)
+                    * d.powi(6)
+                    / 720.0);
+    let lon = lon0
+        + (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+            + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1 * c1 + 8.0 * ep2 + 24.0 * t1 * t1) * d.powi(5)
+                / 120.0)
+            / cos_phi1;
+
+    (lon.to_degrees(), lat.to_degrees())
+}
+
+/// Reprojects a single point from `from` to `to`. Returns the point
+/// unchanged when the two CRS are equal.
+pub fn transform_point(point: Point, from: Crs, to: Crs) -> Point {
+    if from == to {
+        return point;
+    }
+    let (x, y) = match (from, to) {
+        (Crs::Geographic, Crs::Utm { zone, north }) => geographic_to_utm(point.x, point.y, zone, north),
+        (Crs::Utm { zone, north }, Crs::Geographic) => utm_to_geographic(point.x, point.y, zone, north),
+        (Crs::Utm { zone: fz, north: fn_ }, Crs::Utm { zone: tz, north: tn }) => {
+            let (lon, lat) = utm_to_geographic(point.x, point.y, fz, fn_);
+            geographic_to_utm(lon, lat, tz, tn)
+        }
+        (Crs::Geographic, Crs::Geographic) => (point.x, point.y),
+    };
+    Point::new(x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utm_round_trip_recovers_original_geographic_point() {
+        let (lon, lat) = (12.345, 45.678);
+        let (easting, northing) = geographic_to_utm(lon, lat, 33, true);
+        let (lon2, lat2) = utm_to_geographic(easting, northing, 33, true);
+        assert!((lon - lon2).abs() < 1e-7);
+        assert!((lat - lat2).abs() < 1e-7);
+    }
+
+    #[test]
+    fn utm_round_trip_recovers_original_geographic_point_southern_hemisphere() {
+        let (lon, lat) = (18.5, -33.9);
+        let (easting, northing) = geographic_to_utm(lon, lat, 34, false);
+        let (lon2, lat2) = utm_to_geographic(easting, northing, 34, false);
+        assert!((lon - lon2).abs() < 1e-7);
+        assert!((lat - lat2).abs() < 1e-7);
+    }
+
+    #[test]
+    fn crs_from_wkt_detects_southern_hemisphere_only_from_the_zone_suffix() {
+        let wkt = r#"PROJCS["WGS_1984_UTM_Zone_33S",GEOGCS["GCS_WGS_1984",DATUM["D_North_American_1983"]]]"#;
+        assert_eq!(crs_from_wkt(wkt), Some(Crs::Utm { zone: 33, north: false }));
+    }
+
+    #[test]
+    fn crs_from_wkt_detects_northern_hemisphere() {
+        let wkt = r#"PROJCS["WGS_1984_UTM_Zone_33N",GEOGCS["GCS_WGS_1984"]]"#;
+        assert_eq!(crs_from_wkt(wkt), Some(Crs::Utm { zone: 33, north: true }));
+    }
+}
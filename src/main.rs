@@ -0,0 +1,63 @@
+mod cli_clip;
+mod clip;
+mod crs;
+mod spatial_index;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use cli_clip::ClipCliArgs;
+
+use crate::clip::{clip, ClipOptions, Strategy};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    tool: Tool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Tool {
+    /// Clips points according to polygon(s) defined in a given
+    /// shapefile
+    Clip(ClipCliArgs),
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    Ok(match &cli.tool {
+        Tool::Clip(args) => {
+            let lasfile = &args.input;
+            let shapefile = &args.shapefile;
+            let outfile = &args.output;
+            let strategy = if let Some(k) = args.at_least {
+                Strategy::AtLeast(k)
+            } else if args.xor {
+                Strategy::Xor
+            } else if args.intersect {
+                Strategy::Intersection
+            } else {
+                Strategy::Union
+            };
+            let external = args.external;
+            let nthreads = args.threads;
+            let chunk_size = args.chunk_size;
+            clip(
+                lasfile,
+                shapefile,
+                outfile,
+                strategy,
+                external,
+                ClipOptions {
+                    nthreads,
+                    read_chunk: chunk_size,
+                    shapefile_crs: args.shapefile_crs.clone(),
+                    las_crs: args.las_crs.clone(),
+                    buffer: args.buffer,
+                    corridor: args.corridor,
+                    simplify: args.simplify,
+                },
+            )?
+        }
+    })
+}
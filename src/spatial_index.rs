@@ -0,0 +1,167 @@
+//! Spatial acceleration structures built once per `clip` run so that the
+//! per-point containment test does not have to scan every edge of every
+//! polygon.
+//!
+//! Two layers are combined: an R-tree over each polygon's bounding box so a
+//! query point only needs to consider polygons it could plausibly be inside,
+//! and, per polygon, a uniform bucketing of edges by their `y` span so the
+//! winding-number test only visits edges whose vertical extent actually
+//! crosses the query point's `y`.
+use rstar::{Envelope, PointDistance, RTree, RTreeObject, AABB};
+use shapefile::{Point, Polygon, PolygonRing};
+
+/// Maximum number of `y` buckets built per polygon; very small polygons get
+/// fewer, proportional to their edge count.
+const MAX_Y_BUCKETS: usize = 1024;
+
+struct PolygonBBox {
+    index: usize,
+    min: [f64; 2],
+    max: [f64; 2],
+}
+
+impl RTreeObject for PolygonBBox {
+    type Envelope = AABB<[f64; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_corners(self.min, self.max)
+    }
+}
+
+impl PointDistance for PolygonBBox {
+    fn distance_2(&self, point: &[f64; 2]) -> f64 {
+        self.envelope().distance_2(point)
+    }
+
+    fn contains_point(&self, point: &[f64; 2]) -> bool {
+        self.envelope().contains_point(point)
+    }
+}
+
+/// Edges of one polygon (across all of its rings), bucketed by `y` span.
+struct EdgeBuckets {
+    y_min: f64,
+    bucket_height: f64,
+    buckets: Vec<Vec<(Point, Point)>>,
+}
+
+impl EdgeBuckets {
+    fn build(edges: &[(Point, Point)]) -> EdgeBuckets {
+        let mut y_min = f64::INFINITY;
+        let mut y_max = f64::NEG_INFINITY;
+        for &(p1, p2) in edges {
+            y_min = y_min.min(p1.y).min(p2.y);
+            y_max = y_max.max(p1.y).max(p2.y);
+        }
+        if !y_min.is_finite() || !y_max.is_finite() {
+            return EdgeBuckets {
+                y_min: 0.0,
+                bucket_height: 1.0,
+                buckets: Vec::new(),
+            };
+        }
+
+        let num_buckets = edges.len().max(1).min(MAX_Y_BUCKETS);
+        let span = (y_max - y_min).max(f64::EPSILON);
+        let bucket_height = span / num_buckets as f64;
+        let mut buckets = vec![Vec::new(); num_buckets];
+        for &(p1, p2) in edges {
+            let (lo, hi) = (p1.y.min(p2.y), p1.y.max(p2.y));
+            let first = (((lo - y_min) / bucket_height) as usize).min(num_buckets - 1);
+            let last = (((hi - y_min) / bucket_height) as usize).min(num_buckets - 1);
+            for bucket in &mut buckets[first..=last] {
+                bucket.push((p1, p2));
+            }
+        }
+        EdgeBuckets {
+            y_min,
+            bucket_height,
+            buckets,
+        }
+    }
+
+    fn edges_at(&self, y: f64) -> &[(Point, Point)] {
+        if self.buckets.is_empty() {
+            return &[];
+        }
+        let idx = (((y - self.y_min) / self.bucket_height) as isize)
+            .clamp(0, self.buckets.len() as isize - 1) as usize;
+        &self.buckets[idx]
+    }
+}
+
+/// All edges of a polygon (every ring, outer and inner/hole alike), as
+/// directed `(p1, p2)` pairs in their original ring order. The winding
+/// number naturally cancels out over a hole's opposite-wound edges, so no
+/// special-casing of `PolygonRing::Inner` is needed here beyond including
+/// its edges.
+fn polygon_edges(poly: &Polygon) -> Vec<(Point, Point)> {
+    poly.rings()
+        .iter()
+        .flat_map(|ring| {
+            let points = match ring {
+                PolygonRing::Outer(pts) => pts,
+                PolygonRing::Inner(pts) => pts,
+            };
+            points.windows(2).map(|w| (w[0], w[1]))
+        })
+        .collect()
+}
+
+fn polygon_bbox(edges: &[(Point, Point)]) -> ([f64; 2], [f64; 2]) {
+    let mut min = [f64::INFINITY; 2];
+    let mut max = [f64::NEG_INFINITY; 2];
+    for &(p1, p2) in edges {
+        for p in [p1, p2] {
+            min[0] = min[0].min(p.x);
+            min[1] = min[1].min(p.y);
+            max[0] = max[0].max(p.x);
+            max[1] = max[1].max(p.y);
+        }
+    }
+    (min, max)
+}
+
+/// Spatial index over a fixed set of polygons, built once in [`crate::clip::clip`]
+/// after the polygons are loaded and queried once per point.
+pub struct PolygonIndex {
+    rtree: RTree<PolygonBBox>,
+    edges: Vec<EdgeBuckets>,
+}
+
+impl PolygonIndex {
+    pub fn build(polygons: &[Polygon]) -> PolygonIndex {
+        let mut bboxes = Vec::with_capacity(polygons.len());
+        let mut edges = Vec::with_capacity(polygons.len());
+        for (i, poly) in polygons.iter().enumerate() {
+            let poly_edges = polygon_edges(poly);
+            let (min, max) = polygon_bbox(&poly_edges);
+            bboxes.push(PolygonBBox { index: i, min, max });
+            edges.push(EdgeBuckets::build(&poly_edges));
+        }
+        PolygonIndex {
+            rtree: RTree::bulk_load(bboxes),
+            edges,
+        }
+    }
+
+    /// Number of polygons held in the index.
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Indices of the polygons whose bounding box contains `(x, y)`. A
+    /// polygon not returned here is guaranteed not to contain the point;
+    /// one that is returned still needs the winding-number test.
+    pub fn candidates(&self, x: f64, y: f64) -> Vec<usize> {
+        self.rtree
+            .locate_all_at_point(&[x, y])
+            .map(|bbox| bbox.index)
+            .collect()
+    }
+
+    /// Edges of polygon `index` whose `y` span crosses `y`.
+    pub fn edges_near(&self, index: usize, y: f64) -> &[(Point, Point)] {
+        self.edges[index].edges_at(y)
+    }
+}